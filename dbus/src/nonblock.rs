@@ -4,9 +4,19 @@
 //!
 //! Current status:
 //!  * Basic client functionality is up and running, i e, you can make method calls.
-//!  * Receiving messages (e g signals) is possible, but expect a simpler API later.
-//!  * As for server side code, you can use the `tree` module with this connection, but it does not
-//!    support async method handlers.
+//!  * `ConnectionBuilder` lets you set up a connection - bus, requested names, match rules,
+//!    timeout maker - declaratively instead of through a sequence of calls on a live connection.
+//!  * Receiving messages (e g signals) can be done either through `MatchingReceiver::start_receive`,
+//!    or, more conveniently, through `Connection::add_match`, which hands you a `Stream` of messages.
+//!  * As for server side code, you can use the `tree` module with this connection, and additionally
+//!    `insert_async_method` lets a handler run as a future, for the (common) case where answering
+//!    a method call itself requires waiting on something async - though only on something whose
+//!    completion itself shows up as a D-Bus message; see the warning on `insert_async_method`.
+//!  * `Proxy::cache_properties` gives you a read-through cache for a remote object's properties,
+//!    kept up to date through a `PropertiesChanged` subscription, for clients that poll properties
+//!    often (e g login/session managers).
+//!  * Each connection tracks the well-known names it owns; see `owned_names`, `name_acquired`
+//!    and `name_lost`.
 //!
 //! You're probably going to need a companion crate - dbus-tokio - for this connection to make sense.
 //! (Although you can also just call read_write and process_all at regular intervals, and possibly
@@ -16,17 +26,25 @@
 use crate::{Error, Message};
 use crate::channel::{MatchingReceiver, Channel, Sender, Token};
 use crate::strings::{BusName, Path, Interface, Member};
-use crate::arg::{AppendAll, ReadAll, IterAppend};
-use crate::message::MatchRule;
+use crate::arg::{AppendAll, ReadAll, IterAppend, RefArg, Variant, PropMap};
+use crate::message::{MatchRule, MessageType};
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::{task, pin, mem};
 use std::cell::RefCell;
 use std::time::Duration;
 use crate::filters::Filters;
 use std::future::Future;
 use std::time::Instant;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use futures::stream::{FuturesUnordered, StreamExt};
+
+/// Key used to look up a registered async method handler: object path, interface and member name.
+type AsyncMethodKey = (Path<'static>, Interface<'static>, Member<'static>);
+
+/// How long an async method handler (see `insert_async_method`) is given to resolve before
+/// it's timed out and replied to with an error, when a `TimeoutMakerCb` is configured.
+const ASYNC_METHOD_TIMEOUT: Duration = Duration::from_secs(10);
 
 
 mod generated_org_freedesktop_notifications;
@@ -72,6 +90,10 @@ pub struct LocalConnection {
     filters: RefCell<Filters<LocalFilterCb>>,
     replies: RefCell<Replies<LocalRepliesCb>>,
     timeout_maker: Option<TimeoutMakerCb>,
+    async_methods: RefCell<HashMap<AsyncMethodKey, LocalAsyncMethodCb>>,
+    pending_replies: RefCell<FuturesUnordered<LocalPendingReply>>,
+    weak_self: RefCell<Weak<LocalConnection>>,
+    owned_names: RefCell<HashSet<BusName<'static>>>,
 }
 
 /// A connection to D-Bus, async version, which is Send but not Sync.
@@ -80,6 +102,10 @@ pub struct Connection {
     filters: RefCell<Filters<FilterCb>>,
     replies: RefCell<Replies<RepliesCb>>,
     timeout_maker: Option<TimeoutMakerCb>,
+    async_methods: RefCell<HashMap<AsyncMethodKey, AsyncMethodCb>>,
+    pending_replies: RefCell<FuturesUnordered<PendingReply>>,
+    weak_self: RefCell<Weak<Connection>>,
+    owned_names: RefCell<HashSet<BusName<'static>>>,
 }
 
 /// A connection to D-Bus, Send + Sync + async version
@@ -88,15 +114,23 @@ pub struct SyncConnection {
     filters: Mutex<Filters<SyncFilterCb>>,
     replies: Mutex<Replies<SyncRepliesCb>>,
     timeout_maker: Option<TimeoutMakerCb>,
+    async_methods: Mutex<HashMap<AsyncMethodKey, SyncAsyncMethodCb>>,
+    pending_replies: Mutex<FuturesUnordered<SyncPendingReply>>,
+    weak_self: Mutex<Weak<SyncConnection>>,
+    owned_names: Mutex<HashSet<BusName<'static>>>,
 }
 
-use stdintf::org_freedesktop_dbus::DBus;
+use stdintf::org_freedesktop_dbus::{DBus, Properties};
 
 macro_rules! connimpl {
-     ($c: ident, $cb: ident, $rcb: ident $(, $ss:tt)*) =>  {
+     ($c: ident, $cb: ident, $rcb: ident, $acb: ident, $pfut: ident $(, $ss:tt)*) =>  {
 
 type
     $cb = Box<dyn FnMut(Message, &$c) -> bool $(+ $ss)* + 'static>;
+type
+    $acb = Arc<dyn Fn(Arc<$c>, Message) -> pin::Pin<Box<dyn Future<Output = Message> $(+ $ss)* + 'static>> $(+ $ss)* + 'static>;
+type
+    $pfut = pin::Pin<Box<dyn Future<Output = ()> $(+ $ss)* + 'static>>;
 type
     $rcb = Box<dyn FnOnce(Message, &$c) $(+ $ss)* + 'static>;
 
@@ -107,6 +141,10 @@ impl From<Channel> for $c {
             replies: Default::default(),
             filters: Default::default(),
             timeout_maker: None,
+            async_methods: Default::default(),
+            pending_replies: Default::default(),
+            weak_self: Default::default(),
+            owned_names: Default::default(),
         }
     }
 }
@@ -144,6 +182,8 @@ impl NonblockReply for $c {
     fn set_timeout_maker(&mut self, f: Option<TimeoutMakerCb>) -> Option<TimeoutMakerCb> {
         mem::replace(&mut self.timeout_maker, f)
     }
+    fn note_name_owned(&self, name: BusName<'static>) { self.owned_names_mut().insert(name); }
+    fn note_name_lost(&self, name: &BusName<'static>) { self.owned_names_mut().remove(name); }
 }
 
 
@@ -155,6 +195,65 @@ impl Process for $c {
                 return;
             }
         }
+        // NameAcquired/NameLost are unicast straight to us by the bus (no AddMatch needed),
+        // so we can keep owned_names in sync here instead of relying on callers to do it
+        // themselves after subscribing via name_acquired/name_lost. The bus also sends us a
+        // NameAcquired for our own unique name right after connecting, before any well-known
+        // name is ever requested - that's not a well-known name, so it's excluded here.
+        // The sender is checked too - anyone could otherwise address a forged signal with this
+        // interface and member straight at our unique name and have us believe it came from
+        // the bus driver.
+        if msg.msg_type() == MessageType::Signal && msg.interface().as_deref() == Some("org.freedesktop.DBus")
+            && msg.sender().as_deref() == Some("org.freedesktop.DBus") {
+            if let Ok(name) = msg.read1::<&str>() {
+                if name != self.channel.unique_name().unwrap_or("") {
+                    match msg.member().as_deref() {
+                        Some("NameAcquired") => { self.note_name_owned(BusName::from(name).into_static()); }
+                        Some("NameLost") => { self.note_name_lost(&BusName::from(name).into_static()); }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if msg.msg_type() == MessageType::MethodCall {
+            if let Some(key) = async_method_key(&msg) {
+                let handler = self.async_methods_mut().get(&key).cloned();
+                // `self_ref` is only `Some` for connections built with `new_for_server`; for
+                // any other construction, fall through to the normal dispatch below (which
+                // replies with the standard "unknown method" error) instead of invoking a
+                // handler we have no way to drive.
+                if let (Some(handler), Some(conn)) = (handler, self.self_ref()) {
+                    let no_reply = msg.get_no_reply();
+                    let timeout_msg = msg.clone();
+                    let fut = handler(conn.clone(), msg);
+                    let timeoutfn = self.timeout_maker();
+                    self.pending_mut().push(Box::pin(async move {
+                        // Bound the handler by the same timeout maker client calls use. Without
+                        // this, a handler stuck on the no-op-waker limitation (see the warning
+                        // on `insert_async_method`) would wedge forever with no diagnostic, and
+                        // the remote caller would never get a reply at all.
+                        let deadline = if let Some(tfn) = timeoutfn {
+                            tfn(Instant::now() + ASYNC_METHOD_TIMEOUT)
+                        } else {
+                            Box::pin(futures::future::pending())
+                        };
+                        let reply = match futures::future::select(fut, deadline).await {
+                            futures::future::Either::Left((reply, _)) => reply,
+                            futures::future::Either::Right(_) => timeout_msg.error(
+                                &"org.freedesktop.DBus.Error.Timeout".into(),
+                                "Async method handler did not complete in time",
+                            ),
+                        };
+                        // A one-way call (NO_REPLY_EXPECTED) doesn't want a method return/error
+                        // sent back, same as the synchronous `tree` dispatch path.
+                        if !no_reply {
+                            let _ = conn.send(reply);
+                        }
+                    }));
+                    return;
+                }
+            }
+        }
         let ff = self.filters_mut().remove_matching(&msg);
         if let Some(mut ff) = ff {
             if ff.2(msg, self) {
@@ -164,6 +263,15 @@ impl Process for $c {
             let _ = self.send(reply);
         }
     }
+
+    fn process_pending_replies(&self) {
+        // A no-op waker means these futures only make progress when something else causes
+        // `process_all` to run again - see the warning on `insert_async_method`.
+        let waker = futures::task::noop_waker_ref();
+        let mut cx = task::Context::from_waker(waker);
+        let mut pending = self.pending_mut();
+        while let task::Poll::Ready(Some(())) = pending.poll_next_unpin(&mut cx) {}
+    }
 }
 
 impl $c {
@@ -179,28 +287,65 @@ impl $c {
     /// Request a name on the D-Bus.
     ///
     /// For detailed information on the flags and return values, see the libdbus documentation.
+    /// On success, the name is added to `owned_names` if we ended up owning it outright
+    /// (`PrimaryOwner` or `AlreadyOwner`) - not if we were merely queued behind another owner.
     pub async fn request_name<'a, N: Into<BusName<'a>>>(&self, name: N, allow_replacement: bool, replace_existing: bool, do_not_queue: bool)
     -> Result<stdintf::org_freedesktop_dbus::RequestNameReply, Error> {
+        let name = name.into().into_static();
         let flags: u32 =
             if allow_replacement { 1 } else { 0 } +
             if replace_existing { 2 } else { 0 } +
             if do_not_queue { 4 } else { 0 };
-        let r = self.dbus_proxy().request_name(&name.into(), flags).await?;
+        let r = self.dbus_proxy().request_name(&name, flags).await?;
         use stdintf::org_freedesktop_dbus::RequestNameReply::*;
-        let all = [PrimaryOwner, InQueue, Exists, AlreadyOwner];
-        all.iter().find(|x| **x as u32 == r).copied().ok_or_else(||
-            crate::Error::new_failed("Invalid reply from DBus server")
-        )
+        let reply = parse_request_name_reply(r)?;
+        if let PrimaryOwner | AlreadyOwner = reply {
+            self.note_name_owned(name);
+        }
+        Ok(reply)
     }
 
     /// Release a previously requested name on the D-Bus.
+    ///
+    /// On a `Released` reply, the name is removed from `owned_names`.
     pub async fn release_name<'a, N: Into<BusName<'a>>>(&self, name: N) -> Result<stdintf::org_freedesktop_dbus::ReleaseNameReply, Error> {
-        let r = self.dbus_proxy().release_name(&name.into()).await?;
+        let name = name.into().into_static();
+        let r = self.dbus_proxy().release_name(&name).await?;
         use stdintf::org_freedesktop_dbus::ReleaseNameReply::*;
         let all = [Released, NonExistent, NotOwner];
-        all.iter().find(|x| **x as u32 == r).copied().ok_or_else(||
+        let reply = all.iter().find(|x| **x as u32 == r).copied().ok_or_else(||
             crate::Error::new_failed("Invalid reply from DBus server")
-        )
+        )?;
+        if let Released = reply {
+            self.note_name_lost(&name);
+        }
+        Ok(reply)
+    }
+
+    /// Returns the well-known names currently owned by this connection.
+    ///
+    /// This is the connection's own bookkeeping - it does not make a round trip to the bus.
+    /// It starts from what `request_name`/`release_name` observed, and stays correct after
+    /// that because `process_one` also watches for `NameAcquired`/`NameLost` (the bus sends
+    /// both straight to us, without needing a match rule), so a name taken by another owner
+    /// after `allow_replacement` is reflected here as soon as the next message is processed.
+    pub fn owned_names(&self) -> Vec<BusName<'static>> {
+        self.owned_names_mut().iter().cloned().collect()
+    }
+
+    /// Subscribes to the `NameAcquired` signal, sent by the bus whenever this connection gains
+    /// ownership of a well-known name. `owned_names` is kept up to date regardless of whether
+    /// anything is subscribed; use this when you need to react to the event itself.
+    pub async fn name_acquired(&self) -> Result<MsgMatch<'_, Self>, Error> {
+        self.add_match(MatchRule::new_signal("org.freedesktop.DBus", "NameAcquired")).await
+    }
+
+    /// Subscribes to the `NameLost` signal, sent by the bus whenever this connection loses
+    /// ownership of a well-known name (e g to another owner after `allow_replacement`).
+    /// `owned_names` is kept up to date regardless of whether anything is subscribed; use
+    /// this when you need to react to the event itself.
+    pub async fn name_lost(&self) -> Result<MsgMatch<'_, Self>, Error> {
+        self.add_match(MatchRule::new_signal("org.freedesktop.DBus", "NameLost")).await
     }
 
     /// Adds a new match to the connection, without setting up a callback when this message arrives.
@@ -212,29 +357,309 @@ impl $c {
     pub async fn remove_match_no_cb(&self, match_str: &str) -> Result<(), Error> {
         self.dbus_proxy().remove_match(match_str).await
     }
+
+    /// Subscribes to the given match rule and returns a `Stream` of the matching messages.
+    ///
+    /// The match rule is registered with the bus (as if by `add_match_no_cb`) and a filter
+    /// callback is installed to forward matching messages into a bounded channel; the
+    /// returned `MsgMatch` is the receiving end of that channel. When it is dropped, the
+    /// filter callback and the match rule are both torn down automatically, so there is no
+    /// need to call `stop_receive`/`remove_match_no_cb` yourself.
+    pub async fn add_match(&self, match_rule: MatchRule<'static>) -> Result<MsgMatch<'_, Self>, Error> {
+        let match_str = match_rule.match_str();
+        self.add_match_no_cb(&match_str).await?;
+        // zbus uses the same default of 64 queued messages for its MessageStream.
+        let (mut tx, rx) = futures::channel::mpsc::channel(64);
+        let token = self.start_receive(match_rule, Box::new(move |msg, _: &Self| {
+            // Note: this closure runs inside process_one while filters_mut() is borrowed,
+            // so it must not call back into the connection - a non-blocking try_send is fine.
+            let _ = tx.try_send(msg);
+            true
+        }));
+        Ok(MsgMatch { connection: self, token: Some(token), match_str, rx })
+    }
+
+    /// Creates a connection meant to be used with `insert_async_method`.
+    ///
+    /// Async method handlers are driven to completion across multiple calls to
+    /// `process_all`, so they need a way to reach the connection itself (e g to make
+    /// further calls on a `Proxy` while handling a request) that outlives any single
+    /// `process_one` call. Wrapping the connection in an `Arc` up front, as this
+    /// constructor does, gives the handler future exactly that.
+    pub fn new_for_server(channel: Channel) -> Arc<Self> {
+        Arc::new_cyclic(|weak| {
+            let c = Self::from(channel);
+            c.weak_self_set(weak.clone());
+            c
+        })
+    }
+
+    /// Returns `Some` only if this connection was constructed with `new_for_server`.
+    fn self_ref(&self) -> Option<Arc<Self>> {
+        self.weak_self_get().upgrade()
+    }
+
+    /// Registers an async method handler for the given object path, interface and member.
+    ///
+    /// Unlike the synchronous handlers supported by the `tree` module, the handler `f` returns
+    /// a future; once it resolves, the result is turned into a method return (or, on error,
+    /// an error reply) carrying the original call's reply serial, and sent back automatically.
+    ///
+    /// Requires a connection created with `new_for_server` - there's nowhere else to keep the
+    /// handler future alive across `process_all` calls. Registering a handler on a connection
+    /// built any other way is not a programming error in itself: matching method calls simply
+    /// fall through to the default "unknown method" reply instead of invoking it, since there
+    /// is no way to drive the handler to completion.
+    ///
+    /// **Warning:** `f`'s future is polled with a no-op waker, and only gets re-polled when
+    /// `process_all` runs again for some other reason - i e when another message arrives on
+    /// this connection. That happens naturally if the future is waiting on another D-Bus call
+    /// (the reply is itself a message), but if it awaits anything else - a timer, a channel
+    /// from another thread, unrelated I/O - its waker fires into the void and the handler stalls
+    /// until the next unrelated message shows up, if ever. Only await things whose completion is
+    /// itself observable as a new D-Bus message. If the connection has a `timeout_maker` set,
+    /// a handler still pending past `ASYNC_METHOD_TIMEOUT` is replied to with a timeout error
+    /// the next time `process_all` runs after the deadline (the deadline is polled with the same
+    /// no-op waker); without one, a stalled handler hangs indefinitely, same as an unbounded
+    /// client-side call would. A connection from `new_for_server` starts without a timeout
+    /// maker, and `set_timeout_maker` needs `&mut self`, which the returned `Arc` does not hand
+    /// out while the connection's own weak self-reference exists.
+    pub fn insert_async_method<I, M, R, F, Fut>(self: &Arc<Self>, path: Path<'static>, iface: I, member: M, f: F)
+    where
+        I: Into<Interface<'static>>,
+        M: Into<Member<'static>>,
+        R: AppendAll + 'static,
+        F: Fn(Arc<Self>, Message) -> Fut $(+ $ss)* + 'static,
+        Fut: Future<Output = Result<R, Error>> $(+ $ss)* + 'static,
+    {
+        let cb: $acb = Arc::new(move |conn, msg: Message| {
+            let fut = f(conn, msg.clone());
+            Box::pin(async move {
+                match fut.await {
+                    Ok(r) => {
+                        let mut reply = msg.method_return();
+                        r.append(&mut IterAppend::new(&mut reply));
+                        reply
+                    }
+                    Err(e) => msg.error(&e.name().unwrap_or("org.freedesktop.DBus.Error.Failed").into(), &e.message().unwrap_or("")),
+                }
+            }) as pin::Pin<Box<dyn Future<Output = Message> $(+ $ss)* + 'static>>
+        });
+        self.async_methods_mut().insert((path, iface.into(), member.into()), cb);
+    }
+
+    /// Removes a previously registered async method handler.
+    pub fn remove_async_method<I: Into<Interface<'static>>, M: Into<Member<'static>>>(&self, path: Path<'static>, iface: I, member: M) -> bool {
+        self.async_methods_mut().remove(&(path, iface.into(), member.into())).is_some()
+    }
 }
 
+impl SubscribeMatch for $c {
+    fn subscribe_match<'s>(&'s self, match_rule: MatchRule<'static>)
+    -> pin::Pin<Box<dyn Future<Output = Result<MsgMatch<'s, Self>, Error>> + 's>> {
+        Box::pin(self.add_match(match_rule))
+    }
+}
 
     }
 }
 
-connimpl!(Connection, FilterCb, RepliesCb, Send);
-connimpl!(LocalConnection, LocalFilterCb, LocalRepliesCb);
-connimpl!(SyncConnection, SyncFilterCb, SyncRepliesCb, Send);
+connimpl!(Connection, FilterCb, RepliesCb, AsyncMethodCb, PendingReply, Send);
+connimpl!(LocalConnection, LocalFilterCb, LocalRepliesCb, LocalAsyncMethodCb, LocalPendingReply);
+connimpl!(SyncConnection, SyncFilterCb, SyncRepliesCb, SyncAsyncMethodCb, SyncPendingReply, Send);
 
 impl Connection {
     fn filters_mut(&self) -> std::cell::RefMut<Filters<FilterCb>> { self.filters.borrow_mut() }
     fn replies_mut(&self) -> std::cell::RefMut<Replies<RepliesCb>> { self.replies.borrow_mut() }
+    fn async_methods_mut(&self) -> std::cell::RefMut<HashMap<AsyncMethodKey, AsyncMethodCb>> { self.async_methods.borrow_mut() }
+    fn pending_mut(&self) -> std::cell::RefMut<FuturesUnordered<PendingReply>> { self.pending_replies.borrow_mut() }
+    fn weak_self_get(&self) -> Weak<Connection> { self.weak_self.borrow().clone() }
+    fn weak_self_set(&self, w: Weak<Connection>) { *self.weak_self.borrow_mut() = w; }
+    fn owned_names_mut(&self) -> std::cell::RefMut<HashSet<BusName<'static>>> { self.owned_names.borrow_mut() }
 }
 
 impl LocalConnection {
     fn filters_mut(&self) -> std::cell::RefMut<Filters<LocalFilterCb>> { self.filters.borrow_mut() }
     fn replies_mut(&self) -> std::cell::RefMut<Replies<LocalRepliesCb>> { self.replies.borrow_mut() }
+    fn async_methods_mut(&self) -> std::cell::RefMut<HashMap<AsyncMethodKey, LocalAsyncMethodCb>> { self.async_methods.borrow_mut() }
+    fn pending_mut(&self) -> std::cell::RefMut<FuturesUnordered<LocalPendingReply>> { self.pending_replies.borrow_mut() }
+    fn weak_self_get(&self) -> Weak<LocalConnection> { self.weak_self.borrow().clone() }
+    fn weak_self_set(&self, w: Weak<LocalConnection>) { *self.weak_self.borrow_mut() = w; }
+    fn owned_names_mut(&self) -> std::cell::RefMut<HashSet<BusName<'static>>> { self.owned_names.borrow_mut() }
 }
 
 impl SyncConnection {
     fn filters_mut(&self) -> std::sync::MutexGuard<Filters<SyncFilterCb>> { self.filters.lock().unwrap() }
     fn replies_mut(&self) -> std::sync::MutexGuard<Replies<SyncRepliesCb>> { self.replies.lock().unwrap() }
+    fn async_methods_mut(&self) -> std::sync::MutexGuard<HashMap<AsyncMethodKey, SyncAsyncMethodCb>> { self.async_methods.lock().unwrap() }
+    fn pending_mut(&self) -> std::sync::MutexGuard<FuturesUnordered<SyncPendingReply>> { self.pending_replies.lock().unwrap() }
+    fn weak_self_get(&self) -> Weak<SyncConnection> { self.weak_self.lock().unwrap().clone() }
+    fn weak_self_set(&self, w: Weak<SyncConnection>) { *self.weak_self.lock().unwrap() = w; }
+    fn owned_names_mut(&self) -> std::sync::MutexGuard<HashSet<BusName<'static>>> { self.owned_names.lock().unwrap() }
+}
+
+fn async_method_key(msg: &Message) -> Option<AsyncMethodKey> {
+    Some((msg.path()?.into_static(), msg.interface()?.into_static(), msg.member()?.into_static()))
+}
+
+/// Maps a raw `RequestName` reply code to its enum, shared by `request_name` and
+/// `ConnectionBuilder::build`.
+fn parse_request_name_reply(r: u32) -> Result<stdintf::org_freedesktop_dbus::RequestNameReply, Error> {
+    use stdintf::org_freedesktop_dbus::RequestNameReply::*;
+    let all = [PrimaryOwner, InQueue, Exists, AlreadyOwner];
+    all.iter().find(|x| **x as u32 == r).copied().ok_or_else(||
+        crate::Error::new_failed("Invalid reply from DBus server")
+    )
+}
+
+/// A `Stream` of messages matching a `MatchRule`, as returned by e g `Connection::add_match`.
+///
+/// Dropping this stream unregisters the filter callback and removes the match rule from the
+/// bus, so there's no need to call `stop_receive` or `remove_match_no_cb` yourself.
+pub struct MsgMatch<'a, C> {
+    connection: &'a C,
+    token: Option<Token>,
+    match_str: String,
+    rx: futures::channel::mpsc::Receiver<Message>,
+}
+
+impl<'a, C: MatchingReceiver + Sender> Drop for MsgMatch<'a, C> {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            self.connection.stop_receive(token);
+        }
+        // Fire-and-forget: we're in Drop, so there's no way to await the reply, and
+        // nothing sensible to do with it (or its error) even if we could.
+        let msg = Message::method_call(
+            &BusName::from("org.freedesktop.DBus"),
+            &Path::from("/org/freedesktop/DBus"),
+            &Interface::from("org.freedesktop.DBus"),
+            &Member::from("RemoveMatch"),
+        ).append1(&self.match_str);
+        let _ = self.connection.send(msg);
+    }
+}
+
+impl<'a, C> futures::stream::Stream for MsgMatch<'a, C> {
+    type Item = Message;
+    fn poll_next(self: pin::Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Option<Message>> {
+        pin::Pin::new(&mut self.get_mut().rx).poll_next(ctx)
+    }
+}
+
+/// A single well-known name to request, together with the flags to request it with.
+struct BuilderName {
+    name: String,
+    allow_replacement: bool,
+    replace_existing: bool,
+    do_not_queue: bool,
+}
+
+/// A builder for connections to D-Bus.
+///
+/// This collects everything that otherwise has to be done imperatively, and in a
+/// particular order, on a freshly created connection - which bus to connect to, which
+/// well-known names to request, which match rules to install, and the timeout maker - and
+/// performs all of it inside `build`, giving a single fallible entry point instead of a
+/// sequence of calls that are easy to get wrong (e g requesting a name before the timeout
+/// maker is set, or before the channel is actually live).
+///
+/// ```no_run
+/// # async fn f() -> Result<dbus::nonblock::SyncConnection, dbus::Error> {
+/// use dbus::nonblock::ConnectionBuilder;
+/// ConnectionBuilder::session()
+///     .request_name("com.example.MyService", false, true, false)
+///     .build().await
+/// # }
+/// ```
+pub struct ConnectionBuilder {
+    bus: crate::channel::BusType,
+    names: Vec<BuilderName>,
+    match_rules: Vec<MatchRule<'static>>,
+    timeout_maker: Option<TimeoutMakerCb>,
+}
+
+impl ConnectionBuilder {
+    /// Starts building a connection to the given bus.
+    pub fn new(bus: crate::channel::BusType) -> Self {
+        ConnectionBuilder { bus, names: vec![], match_rules: vec![], timeout_maker: None }
+    }
+
+    /// Starts building a connection to the session bus.
+    pub fn session() -> Self { Self::new(crate::channel::BusType::Session) }
+
+    /// Starts building a connection to the system bus.
+    pub fn system() -> Self { Self::new(crate::channel::BusType::System) }
+
+    /// Requests a well-known name once the connection is built.
+    ///
+    /// For detailed information on the flags, see the libdbus documentation.
+    pub fn request_name<'a, N: Into<BusName<'a>>>(mut self, name: N, allow_replacement: bool, replace_existing: bool, do_not_queue: bool) -> Self {
+        self.names.push(BuilderName { name: name.into().to_string(), allow_replacement, replace_existing, do_not_queue });
+        self
+    }
+
+    /// Installs a match rule once the connection is built.
+    pub fn match_rule(mut self, match_rule: MatchRule<'static>) -> Self {
+        self.match_rules.push(match_rule);
+        self
+    }
+
+    /// Sets the timeout maker, used to time out method calls that never get a reply.
+    pub fn timeout_maker(mut self, f: TimeoutMakerCb) -> Self {
+        self.timeout_maker = Some(f);
+        self
+    }
+
+    /// Connects to the bus and performs all the requested setup, in the right order.
+    ///
+    /// The connection hasn't been handed to a reactor yet at this point - that's the whole
+    /// point of doing setup here instead of on a live connection - so nothing would otherwise
+    /// ever call `process_all` to let the setup calls' replies arrive. `build` pumps
+    /// `read_write`/`process_all` on the fresh connection itself until each call resolves.
+    pub async fn build<C: From<Channel> + NonblockReply + Process>(self) -> Result<C, Error> {
+        let channel = Channel::get_private(self.bus)?;
+        let mut c: C = channel.into();
+        c.set_timeout_maker(self.timeout_maker);
+        {
+            let proxy = Proxy::new("org.freedesktop.DBus", "/org/freedesktop/DBus", Duration::from_secs(10), &c);
+            for n in &self.names {
+                let flags: u32 =
+                    if n.allow_replacement { 1 } else { 0 } +
+                    if n.replace_existing { 2 } else { 0 } +
+                    if n.do_not_queue { 4 } else { 0 };
+                let r = Self::drive(&c, proxy.request_name(&n.name, flags))?;
+                // `build` is generic over `C`, so it can't call the concrete type's own
+                // `request_name` wrapper; go through `NonblockReply` instead so `owned_names`
+                // still reflects names requested through the builder.
+                use stdintf::org_freedesktop_dbus::RequestNameReply::*;
+                if let PrimaryOwner | AlreadyOwner = parse_request_name_reply(r)? {
+                    c.note_name_owned(BusName::from(n.name.as_str()).into_static());
+                }
+            }
+            for m in self.match_rules {
+                Self::drive(&c, proxy.add_match(&m.match_str()))?;
+            }
+        }
+        Ok(c)
+    }
+
+    /// Polls `fut` to completion, pumping I/O on `c` between polls.
+    ///
+    /// Used only during `build`, before `c` has been handed to a reactor: there is nobody
+    /// else to call `read_write`/`process_all` on our behalf, so we do it ourselves in a
+    /// tight loop instead of relying on a waker that nothing would ever wake.
+    fn drive<C: Process, R, Fut>(c: &C, mut fut: Fut) -> Result<R, Error>
+    where Fut: Future<Output = Result<R, Error>> + Unpin {
+        let waker = futures::task::noop_waker_ref();
+        let mut cx = task::Context::from_waker(waker);
+        loop {
+            if let task::Poll::Ready(r) = Future::poll(pin::Pin::new(&mut fut), &mut cx) { return r; }
+            let channel: &Channel = c.as_ref();
+            channel.read_write(Some(Duration::from_millis(100))).map_err(|_| Error::new_failed("Connection lost"))?;
+            c.process_all();
+        }
+    }
 }
 
 /// Internal callback for the executor when a timeout needs to be made.
@@ -254,9 +679,22 @@ pub trait NonblockReply {
     fn set_timeout_maker(&mut self, f: Option<TimeoutMakerCb>) -> Option<TimeoutMakerCb>;
     /// Get the internal timeout maker
     fn timeout_maker(&self) -> Option<TimeoutMakerCb>;
+    /// Records that a well-known name is now owned, for `owned_names` bookkeeping.
+    fn note_name_owned(&self, name: BusName<'static>);
+    /// Records that a well-known name is no longer owned, for `owned_names` bookkeeping.
+    fn note_name_lost(&self, name: &BusName<'static>);
 }
 
 
+/// Internal helper trait, implemented for connections that support the `Stream`-based
+/// match subscription from `add_match`, so generic code (such as `Proxy::cache_properties`)
+/// can subscribe without naming the concrete connection type.
+pub trait SubscribeMatch: MatchingReceiver + Sender + Sized {
+    /// See the inherent `add_match` method on `Connection`/`LocalConnection`/`SyncConnection`.
+    fn subscribe_match<'s>(&'s self, match_rule: MatchRule<'static>)
+    -> pin::Pin<Box<dyn Future<Output = Result<MsgMatch<'s, Self>, Error>> + 's>>;
+}
+
 /// Internal helper trait, implemented for connections that process incoming messages.
 pub trait Process: Sender + AsRef<Channel> {
     /// Dispatches all pending messages, without blocking.
@@ -269,10 +707,17 @@ pub trait Process: Sender + AsRef<Channel> {
         while let Some(msg) = c.pop_message() {
             self.process_one(msg);
         }
+        self.process_pending_replies();
     }
 
     /// Dispatches a message.
     fn process_one(&self, msg: Message);
+
+    /// Drives any async method handler futures spawned by `process_one` one step further.
+    ///
+    /// The default implementation does nothing; connections that support async method
+    /// handlers (see `insert_async_method`) override this to poll their pending replies.
+    fn process_pending_replies(&self) {}
 }
 
 /// A struct that wraps a connection, destination and path.
@@ -299,21 +744,32 @@ impl<'a, C> Proxy<'a, C> {
     }
 }
 
-struct MRAwait {
+/// The callback that cancels an in-flight reply given its token, type-erased over the
+/// connection type so it doesn't have to appear in `MethodReply`'s public type.
+type Canceller<'a> = Arc<dyn Fn(Token) + Send + Sync + 'a>;
+
+struct MRAwait<'a> {
     mrouter: MROuter,
     token: Result<Token, ()>,
     timeout: Instant,
-    timeoutfn: Option<TimeoutMakerCb>
+    timeoutfn: Option<TimeoutMakerCb>,
+    canceller: Canceller<'a>,
 }
 
-async fn method_call_await(mra: MRAwait) -> Result<Message, Error> {
+async fn method_call_await(mra: MRAwait<'_>) -> Result<Message, Error> {
     use futures::future;
-    let MRAwait { mrouter, token, timeout, timeoutfn } = mra;
+    let MRAwait { mrouter, token, timeout, timeoutfn, canceller } = mra;
     if token.is_err() { return Err(Error::new_failed("Failed to send message")) };
     let timeout = if let Some(tfn) = timeoutfn { tfn(timeout) } else { Box::pin(future::pending()) };
     match future::select(mrouter, timeout).await {
         future::Either::Left((r, _)) => r,
-        future::Either::Right(_) => Err(Error::new_custom("org.freedesktop.DBus.Error.Timeout", "Timeout waiting for reply")),
+        future::Either::Right(_) => {
+            // The reply can still arrive after this point (the remote doesn't know we gave
+            // up), so unregister the callback - otherwise it, and the reply it's waiting
+            // for, would sit in `replies` forever.
+            if let Ok(t) = token { canceller(t); }
+            Err(Error::new_custom("org.freedesktop.DBus.Error.Timeout", "Timeout waiting for reply"))
+        }
     }
 }
 
@@ -322,8 +778,14 @@ where
     T: NonblockReply,
     C: std::ops::Deref<Target=T>
 {
+    /// A type-erased `cancel_reply` closure borrowing the proxy's connection, so cancellation
+    /// doesn't need to name `C` (or clone it) in anything returned to callers.
+    fn canceller(&'a self) -> Canceller<'a> {
+        let connection: &'a T = &self.connection;
+        Arc::new(move |t: Token| { connection.cancel_reply(t); })
+    }
 
-    fn method_call_setup(&self, msg: Message) -> MRAwait {
+    fn method_call_setup(&'a self, msg: Message) -> MRAwait<'a> {
         let mr = Arc::new(Mutex::new(MRInner::Neither));
         let mrouter = MROuter(mr.clone());
         let f = T::make_f(move |msg: Message, _: &T| {
@@ -335,18 +797,100 @@ where
         let timeout = Instant::now() + self.timeout;
         let token = self.connection.send_with_reply(msg, f);
         let timeoutfn = self.connection.timeout_maker();
-        MRAwait { mrouter, token, timeout, timeoutfn }
+        let canceller = self.canceller();
+        MRAwait { mrouter, token, timeout, timeoutfn, canceller }
     }
 
     /// Make a method call using typed input argument, returns a future that resolves to the typed output arguments.
-    pub fn method_call<'i, 'm, R: ReadAll + 'static, A: AppendAll, I: Into<Interface<'i>>, M: Into<Member<'m>>>(&self, i: I, m: M, args: A)
-    -> MethodReply<R> {
+    pub fn method_call<'i, 'm, R: ReadAll + 'static, A: AppendAll, I: Into<Interface<'i>>, M: Into<Member<'m>>>(&'a self, i: I, m: M, args: A)
+    -> MethodReply<'a, R> {
         let mut msg = Message::method_call(&self.destination, &self.path, &i.into(), &m.into());
         args.append(&mut IterAppend::new(&mut msg));
         let mra = self.method_call_setup(msg);
+        let token = mra.token;
+        let canceller = mra.canceller.clone();
         let r = method_call_await(mra);
         let r = futures::FutureExt::map(r, |r| -> Result<R, Error> { r.and_then(|rmsg| rmsg.read_all()) } );
-        MethodReply::new(r)
+        MethodReply::new(r, token, canceller)
+    }
+}
+
+impl<'a, T, C> Proxy<'a, C>
+where
+    T: NonblockReply + SubscribeMatch,
+    C: std::ops::Deref<Target=T> + Clone,
+{
+    /// Starts an opt-in, read-through cache of this object's properties on the given interface.
+    ///
+    /// This issues a single `Properties.GetAll` call to seed the cache, then subscribes to the
+    /// `PropertiesChanged` signal (like `add_match`) to keep it up to date. Calling this
+    /// repeatedly for the same interface sets up independent caches; there's no sharing.
+    pub async fn cache_properties<I: Into<Interface<'static>>>(&'a self, interface: I) -> Result<PropCache<'a, T, C>, Error> {
+        let interface = interface.into();
+        let values = self.get_all(&interface).await?;
+        let mut match_rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged");
+        match_rule.path = Some(self.path.clone().into_static());
+        // PropertiesChanged is a broadcast with no destination field, so without constraining
+        // the sender too, any other service exposing an object at the same path would have its
+        // signals merged into this cache. The bus resolves a well-known destination name to its
+        // current owner at AddMatch time, so this still works if `destination` isn't unique.
+        match_rule.sender = Some(self.destination.clone().into_static());
+        let changes = self.connection.subscribe_match(match_rule).await?;
+        Ok(PropCache { proxy: self, interface, values: Mutex::new(values), changes: RefCell::new(changes) })
+    }
+}
+
+/// A read-through cache of a single interface's properties on a remote object.
+///
+/// Created by `Proxy::cache_properties`. `cached_get` never blocks or makes a D-Bus call - it
+/// just drains any `PropertiesChanged` signals received so far and reads from the local map.
+/// `get` falls back to a live `Properties.Get` the first time a property hasn't been seen yet.
+pub struct PropCache<'a, T, C> {
+    proxy: &'a Proxy<'a, C>,
+    interface: Interface<'static>,
+    values: Mutex<PropMap>,
+    changes: RefCell<MsgMatch<'a, T>>,
+}
+
+impl<'a, T, C> PropCache<'a, T, C>
+where
+    T: NonblockReply + SubscribeMatch,
+    C: std::ops::Deref<Target=T> + Clone,
+{
+    /// Applies any `PropertiesChanged` signals received so far to the cached values.
+    fn drain_changes(&self) {
+        let waker = futures::task::noop_waker_ref();
+        let mut cx = task::Context::from_waker(waker);
+        let mut changes = self.changes.borrow_mut();
+        while let task::Poll::Ready(Some(msg)) = pin::Pin::new(&mut *changes).poll_next(&mut cx) {
+            if let Ok((iface, changed, invalidated)) = msg.read3::<String, PropMap, Vec<String>>() {
+                if iface.as_str() != &*self.interface { continue; }
+                let mut values = self.values.lock().unwrap();
+                values.extend(changed);
+                for name in invalidated { values.remove(&name); }
+            }
+        }
+    }
+
+    /// Reads a property from the cache without making a D-Bus call.
+    ///
+    /// Returns `None` if the property isn't in the cache: the service left it out of its
+    /// `GetAll` reply and no `PropertiesChanged` for it has arrived, or a `PropertiesChanged`
+    /// signal listed it as invalidated.
+    pub fn cached_get(&self, property_name: &str) -> Option<Variant<Box<dyn RefArg + 'static>>> {
+        self.drain_changes();
+        self.values.lock().unwrap().get(property_name).map(|v| Variant(v.0.box_clone()))
+    }
+
+    /// Reads a property, falling back to a live `Properties.Get` call on a cache miss.
+    ///
+    /// A successful live call is stored in the cache, so later reads of the same property
+    /// don't need to go over the bus again.
+    pub async fn get(&self, property_name: &str) -> Result<Variant<Box<dyn RefArg + 'static>>, Error> {
+        if let Some(v) = self.cached_get(property_name) { return Ok(v); }
+        let v = self.proxy.get(&self.interface, property_name).await?;
+        self.values.lock().unwrap().insert(property_name.to_string(), Variant(v.0.box_clone()));
+        Ok(v)
     }
 }
 
@@ -372,29 +916,60 @@ impl Future for MROuter {
 }
 
 /// Future method reply, used while waiting for a method call reply from the server.
-pub struct MethodReply<T>(pin::Pin<Box<dyn Future<Output=Result<T, Error>> + Send + 'static>>);
+///
+/// Dropping this before it resolves cancels the in-flight call: the reply callback is
+/// unregistered from the connection, so a server that never answers doesn't leak an entry
+/// in the connection's reply table. The same can be done explicitly (without dropping the
+/// whole future) through `abort`.
+pub struct MethodReply<'a, R> {
+    fut: pin::Pin<Box<dyn Future<Output=Result<R, Error>> + Send + 'a>>,
+    pending_cancel: Option<(Token, Canceller<'a>)>,
+}
 
-impl<T> MethodReply<T> {
-    /// Creates a new method reply from a future.
-    fn new<Fut: Future<Output=Result<T, Error>> + Send + 'static>(fut: Fut) -> Self {
-        MethodReply(Box::pin(fut))
+impl<'a, R> MethodReply<'a, R> {
+    /// Creates a new method reply from a future, together with the token and type-erased
+    /// canceller needed to cancel the in-flight reply if the future is dropped or aborted early.
+    fn new<Fut: Future<Output=Result<R, Error>> + Send + 'a>(fut: Fut, token: Result<Token, ()>, canceller: Canceller<'a>) -> Self {
+        MethodReply { fut: Box::pin(fut), pending_cancel: token.ok().map(|t| (t, canceller)) }
+    }
+
+    /// Cancels the in-flight method call, if it hasn't already completed.
+    ///
+    /// After calling this, the `MethodReply` resolves with the reply only if it had already
+    /// arrived; otherwise it resolves with a timeout error once the proxy's timeout passes (if
+    /// the connection has a timeout maker), or never. Drop it rather than polling it further.
+    pub fn abort(&mut self) {
+        if let Some((token, canceller)) = self.pending_cancel.take() {
+            canceller(token);
+        }
     }
 }
 
-impl<T> Future for MethodReply<T> {
-    type Output = Result<T, Error>;
-    fn poll(mut self: pin::Pin<&mut Self>, ctx: &mut task::Context) -> task::Poll<Result<T, Error>> {
-        self.0.as_mut().poll(ctx)
+impl<'a, R> Drop for MethodReply<'a, R> {
+    fn drop(&mut self) { self.abort() }
+}
+
+impl<'a, R> Future for MethodReply<'a, R> {
+    type Output = Result<R, Error>;
+    fn poll(self: pin::Pin<&mut Self>, ctx: &mut task::Context) -> task::Poll<Result<R, Error>> {
+        let this = self.get_mut();
+        let r = this.fut.as_mut().poll(ctx);
+        if r.is_ready() { this.pending_cancel = None; }
+        r
     }
 }
 
-impl<T: 'static> MethodReply<T> {
+impl<'a, R: 'a> MethodReply<'a, R> {
     /// Convenience combinator in case you want to post-process the result after reading it
-    pub fn and_then<T2>(self, f: impl FnOnce(T) -> Result<T2, Error> + Send + Sync + 'static) -> MethodReply<T2> {
-        MethodReply(Box::pin(async move {
-            let x = self.0.await?;
-            f(x)
-        }))
+    pub fn and_then<R2>(mut self, f: impl FnOnce(R) -> Result<R2, Error> + Send + Sync + 'a) -> MethodReply<'a, R2> {
+        let pending_cancel = self.pending_cancel.take();
+        MethodReply {
+            fut: Box::pin(async move {
+                let x = self.await?;
+                f(x)
+            }),
+            pending_cancel,
+        }
     }
 }
 